@@ -0,0 +1,108 @@
+// decimal_json.rs - Lossless JSON serialization of decimal columns
+// Sasaki, Naoki <nsasaki@sal.co.jp> July 25, 2026
+//
+
+use datafusion::arrow::array::{Array, Decimal128Array, Decimal256Array};
+use datafusion::arrow::record_batch::RecordBatch;
+use serde_json::{Map, Value};
+
+use super::schema::{DataSourceSchema, DataType};
+
+/// Applies lossless decimal encoding to a batch of already-encoded JSON `rows`.
+///
+/// The record-batch→JSON encoder builds each row through arrow's default path,
+/// which routes `Decimal128`/`Decimal256` through an `f64` and loses precision
+/// and scale. This pass overwrites the value of every decimal column with its
+/// arbitrary-precision form (see [`cell_value`]); non-decimal columns are left
+/// untouched, so it is a no-op for schemas without decimals. `rows` must line
+/// up with `schema.fields` and `batch` columns, as produced by the encoder.
+pub fn apply_decimal_overrides(
+    batch: &RecordBatch,
+    schema: &DataSourceSchema,
+    rows: &mut [Map<String, Value>],
+) {
+    for (column, field) in schema.fields.iter().enumerate() {
+        if column >= batch.num_columns() {
+            break;
+        }
+
+        let array = batch.column(column);
+        for (row, object) in rows.iter_mut().enumerate() {
+            if let Some(value) = cell_value(&field.data_type, array.as_ref(), row) {
+                object.insert(field.name.clone(), value);
+            }
+        }
+    }
+}
+
+/// Schema-aware JSON value for a single cell of a `Decimal128`/`Decimal256`
+/// column.
+///
+/// Encoding decimals through an `f64` loses precision and scale, so instead we
+/// format the raw backing integer with the `scale` recorded in the schema's
+/// [`super::schema::DecimalType`] and hand the result to `serde_json` as an
+/// arbitrary-precision number (unquoted). Types other than decimals return
+/// `None`, signalling the caller to fall back to its default encoding path.
+pub fn cell_value(data_type: &DataType, array: &dyn Array, row: usize) -> Option<Value> {
+    if array.is_null(row) {
+        // Leave null cells to the default encoder, which omits the key, rather
+        // than forcing an explicit `null` that no other column would emit.
+        return None;
+    }
+
+    match data_type {
+        DataType::Decimal128(decimal_type) => {
+            let value = array.as_any().downcast_ref::<Decimal128Array>()?.value(row);
+            Some(arbitrary_precision(&value.to_string(), decimal_type.scale))
+        }
+        DataType::Decimal256(decimal_type) | DataType::Decimal(decimal_type) => {
+            let value = array.as_any().downcast_ref::<Decimal256Array>()?.value(row);
+            Some(arbitrary_precision(&value.to_string(), decimal_type.scale))
+        }
+        _ => None,
+    }
+}
+
+/// Parses a decimal string into a JSON number, guarding against silent
+/// precision loss.
+///
+/// With `serde_json`'s `arbitrary_precision` feature on, the parsed number
+/// re-serializes to the exact input text. Without it, `from_str` still
+/// succeeds by routing the value through an `f64`, so a large or high-scale
+/// decimal parses without error but loses digits. We detect that by checking
+/// the number round-trips back to the exact formatted text; if it does not,
+/// we keep the value as an unambiguous string rather than emit a rounded
+/// number.
+fn arbitrary_precision(digits: &str, scale: i8) -> Value {
+    let formatted = place_decimal_point(digits, scale);
+
+    match serde_json::from_str::<Value>(&formatted) {
+        Ok(value @ Value::Number(_)) if value.to_string() == formatted => value,
+        _ => Value::String(formatted),
+    }
+}
+
+/// Inserts a decimal point into the raw integer `digits` according to `scale`,
+/// which is the number of fractional digits. A negative scale appends `-scale`
+/// trailing zeros to the integer (i.e. multiplies by `10^-scale`).
+fn place_decimal_point(digits: &str, scale: i8) -> String {
+    let (sign, magnitude) = match digits.strip_prefix('-') {
+        Some(rest) => ("-", rest),
+        None => ("", digits),
+    };
+
+    if scale <= 0 {
+        // No fractional part; pad with trailing zeros for a negative scale.
+        return format!("{sign}{magnitude}{}", "0".repeat((-scale) as usize));
+    }
+
+    let scale = scale as usize;
+    if magnitude.len() > scale {
+        let point = magnitude.len() - scale;
+        format!("{sign}{}.{}", &magnitude[..point], &magnitude[point..])
+    } else {
+        // Left-pad with zeros so the value is smaller than one in magnitude.
+        let zeros = "0".repeat(scale - magnitude.len());
+        format!("{sign}0.{zeros}{magnitude}")
+    }
+}
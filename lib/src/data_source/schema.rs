@@ -2,6 +2,7 @@
 // Sasaki, Naoki <nsasaki@sal.co.jp> January 29, 2023
 //
 
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use datafusion::arrow::{self, datatypes::SchemaRef};
@@ -37,17 +38,84 @@ pub enum DataType {
     Time(TimeType),   // alias as Time32
     Duration(DurationType),
     Interval(IntervalType),
-    String, // variable length string in Unicode with UTF-8 encoding
+    String,      // variable length string in Unicode with UTF-8 encoding
+    LargeString, // alias as LargeUtf8
+    StringView,  // alias as Utf8View
+    Binary,
+    LargeBinary,
+    BinaryView,
+    FixedSizeBinary(i32),
     List(Box<DataType>),
     LargeList(Box<DataType>),
+    ListView(Box<DataType>),
+    LargeListView(Box<DataType>),
+    FixedSizeList(Box<DataType>, i32),
     Map(Box<DataType>, Box<DataType>),
     Struct(Vec<(String, DataType)>),
     Union(UnionType),
+    Dictionary(Box<DataType>, Box<DataType>), // (index type, value type)
+    RunEndEncoded(Box<DataType>, Box<DataType>), // (run-ends type, values type)
 }
 
-impl DataType {
-    fn to_arrow_data_type(&self) -> arrow::datatypes::DataType {
+/// Error raised while converting between the wire `DataType`/schema model and
+/// their `arrow` counterparts.
+#[derive(Debug)]
+pub enum SchemaError {
+    /// A field could not be converted; wraps the underlying cause with the
+    /// offending field name for a descriptive message.
+    Field {
+        name: String,
+        source: Box<SchemaError>,
+    },
+    /// An arrow data type has no representation in the wire schema model.
+    UnsupportedArrowDataType(String),
+    /// A wire data type cannot be realized as an arrow type.
+    UnsupportedDataType(String),
+    /// A `Map` entry field did not carry the expected key/value struct.
+    MalformedMap(String),
+    /// Propagated from the arrow layer.
+    Arrow(arrow::error::ArrowError),
+}
+
+impl std::fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SchemaError::Field { name, source } => write!(f, "field '{name}': {source}"),
+            SchemaError::UnsupportedArrowDataType(data_type) => {
+                write!(f, "unsupported arrow data type: {data_type}")
+            }
+            SchemaError::UnsupportedDataType(data_type) => {
+                write!(f, "unsupported data type: {data_type}")
+            }
+            SchemaError::MalformedMap(data_type) => {
+                write!(f, "expected key/value struct in map entry but found {data_type}")
+            }
+            SchemaError::Arrow(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for SchemaError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match self {
+            SchemaError::Field { source, .. } => Some(source.as_ref()),
+            SchemaError::Arrow(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<arrow::error::ArrowError> for SchemaError {
+    fn from(err: arrow::error::ArrowError) -> Self {
+        SchemaError::Arrow(err)
+    }
+}
+
+impl TryFrom<&DataType> for arrow::datatypes::DataType {
+    type Error = SchemaError;
+
+    fn try_from(data_type: &DataType) -> Result<Self, Self::Error> {
+        Ok(match data_type {
             DataType::Boolean => arrow::datatypes::DataType::Boolean,
             DataType::Int8 => arrow::datatypes::DataType::Int8,
             DataType::Int16 => arrow::datatypes::DataType::Int16,
@@ -78,24 +146,72 @@ impl DataType {
             DataType::Duration(duration_type) => duration_type.clone().into_arrow_duration(),
             DataType::Interval(interval_type) => interval_type.clone().into_arrow_interval(),
             DataType::String => arrow::datatypes::DataType::Utf8,
+            DataType::LargeString => arrow::datatypes::DataType::LargeUtf8,
+            DataType::StringView => arrow::datatypes::DataType::Utf8View,
+            DataType::Binary => arrow::datatypes::DataType::Binary,
+            DataType::LargeBinary => arrow::datatypes::DataType::LargeBinary,
+            DataType::BinaryView => arrow::datatypes::DataType::BinaryView,
+            DataType::FixedSizeBinary(length) => {
+                arrow::datatypes::DataType::FixedSizeBinary(*length)
+            }
             DataType::List(child_type) => {
                 arrow::datatypes::DataType::List(arrow::datatypes::FieldRef::from(
-                    arrow::datatypes::Field::new("item", child_type.to_arrow_data_type(), true),
+                    arrow::datatypes::Field::new(
+                        "item",
+                        arrow::datatypes::DataType::try_from(child_type.as_ref())?,
+                        true,
+                    ),
                 ))
             }
             DataType::LargeList(child_type) => {
                 arrow::datatypes::DataType::LargeList(arrow::datatypes::FieldRef::from(
-                    arrow::datatypes::Field::new("item", child_type.to_arrow_data_type(), true),
+                    arrow::datatypes::Field::new(
+                        "item",
+                        arrow::datatypes::DataType::try_from(child_type.as_ref())?,
+                        true,
+                    ),
+                ))
+            }
+            DataType::ListView(child_type) => {
+                arrow::datatypes::DataType::ListView(arrow::datatypes::FieldRef::from(
+                    arrow::datatypes::Field::new(
+                        "item",
+                        arrow::datatypes::DataType::try_from(child_type.as_ref())?,
+                        true,
+                    ),
+                ))
+            }
+            DataType::LargeListView(child_type) => {
+                arrow::datatypes::DataType::LargeListView(arrow::datatypes::FieldRef::from(
+                    arrow::datatypes::Field::new(
+                        "item",
+                        arrow::datatypes::DataType::try_from(child_type.as_ref())?,
+                        true,
+                    ),
                 ))
             }
+            DataType::FixedSizeList(child_type, length) => {
+                arrow::datatypes::DataType::FixedSizeList(
+                    arrow::datatypes::FieldRef::from(arrow::datatypes::Field::new(
+                        "item",
+                        arrow::datatypes::DataType::try_from(child_type.as_ref())?,
+                        true,
+                    )),
+                    *length,
+                )
+            }
             DataType::Map(key_type, value_type) => arrow::datatypes::DataType::Map(
                 arrow::datatypes::FieldRef::from(arrow::datatypes::Field::new(
                     "entry",
                     arrow::datatypes::DataType::Struct(arrow::datatypes::Fields::from(vec![
-                        arrow::datatypes::Field::new("key", key_type.to_arrow_data_type(), false),
+                        arrow::datatypes::Field::new(
+                            "key",
+                            arrow::datatypes::DataType::try_from(key_type.as_ref())?,
+                            false,
+                        ),
                         arrow::datatypes::Field::new(
                             "value",
-                            value_type.to_arrow_data_type(),
+                            arrow::datatypes::DataType::try_from(value_type.as_ref())?,
                             true,
                         ),
                     ])),
@@ -103,43 +219,69 @@ impl DataType {
                 )),
                 false,
             ),
-            DataType::Struct(fields) => arrow::datatypes::DataType::Struct(
-                fields
-                    .iter()
-                    .map(|(name, data_type)| {
-                        arrow::datatypes::Field::new(name, data_type.to_arrow_data_type(), true)
-                    })
-                    .collect(),
-            ),
+            DataType::Struct(fields) => {
+                let mut struct_fields = Vec::<arrow::datatypes::Field>::with_capacity(fields.len());
+                for (name, data_type) in fields {
+                    struct_fields.push(arrow::datatypes::Field::new(
+                        name,
+                        arrow::datatypes::DataType::try_from(data_type)?,
+                        true,
+                    ));
+                }
+                arrow::datatypes::DataType::Struct(struct_fields.into())
+            }
             DataType::Union(union_type) => {
                 let type_ids = union_type
                     .types
                     .iter()
                     .map(|(type_id, _)| *type_id)
                     .collect::<Vec<i8>>();
-                let fields = union_type
-                    .types
-                    .iter()
-                    .map(|(_, my_data_type)| {
-                        Arc::new(arrow::datatypes::Field::new(
-                            "",
-                            my_data_type.to_arrow_data_type(),
-                            true,
-                        )) as arrow::datatypes::FieldRef
-                    })
-                    .collect::<Vec<arrow::datatypes::FieldRef>>();
+                let mut fields = Vec::<arrow::datatypes::FieldRef>::with_capacity(type_ids.len());
+                for (_, my_data_type) in &union_type.types {
+                    fields.push(Arc::new(arrow::datatypes::Field::new(
+                        "",
+                        arrow::datatypes::DataType::try_from(my_data_type)?,
+                        true,
+                    )) as arrow::datatypes::FieldRef);
+                }
 
                 arrow::datatypes::DataType::Union(
                     arrow::datatypes::UnionFields::new(type_ids, fields),
                     UnionMode::to_arrow_union_mode(&union_type.mode),
                 )
             }
-            DataType::Unknown => arrow::datatypes::DataType::Binary,
-        }
+            DataType::Dictionary(index_type, value_type) => {
+                arrow::datatypes::DataType::Dictionary(
+                    Box::new(arrow::datatypes::DataType::try_from(index_type.as_ref())?),
+                    Box::new(arrow::datatypes::DataType::try_from(value_type.as_ref())?),
+                )
+            }
+            DataType::RunEndEncoded(run_ends_type, values_type) => {
+                arrow::datatypes::DataType::RunEndEncoded(
+                    arrow::datatypes::FieldRef::from(arrow::datatypes::Field::new(
+                        "run_ends",
+                        arrow::datatypes::DataType::try_from(run_ends_type.as_ref())?,
+                        false,
+                    )),
+                    arrow::datatypes::FieldRef::from(arrow::datatypes::Field::new(
+                        "values",
+                        arrow::datatypes::DataType::try_from(values_type.as_ref())?,
+                        true,
+                    )),
+                )
+            }
+            DataType::Unknown => {
+                return Err(SchemaError::UnsupportedDataType("Unknown".to_string()))
+            }
+        })
     }
+}
 
-    fn from_arrow_data_type(arrow_data_type: &arrow::datatypes::DataType) -> DataType {
-        match arrow_data_type {
+impl TryFrom<&arrow::datatypes::DataType> for DataType {
+    type Error = SchemaError;
+
+    fn try_from(arrow_data_type: &arrow::datatypes::DataType) -> Result<Self, Self::Error> {
+        Ok(match arrow_data_type {
             arrow::datatypes::DataType::Boolean => DataType::Boolean,
             arrow::datatypes::DataType::Int8 => DataType::Int8,
             arrow::datatypes::DataType::Int16 => DataType::Int16,
@@ -183,46 +325,73 @@ impl DataType {
                 unit: IntervalUnit::from_arrow_interval_unit(unit),
             }),
             arrow::datatypes::DataType::Utf8 => DataType::String,
+            arrow::datatypes::DataType::LargeUtf8 => DataType::LargeString,
+            arrow::datatypes::DataType::Utf8View => DataType::StringView,
+            arrow::datatypes::DataType::Binary => DataType::Binary,
+            arrow::datatypes::DataType::LargeBinary => DataType::LargeBinary,
+            arrow::datatypes::DataType::BinaryView => DataType::BinaryView,
+            arrow::datatypes::DataType::FixedSizeBinary(length) => {
+                DataType::FixedSizeBinary(*length)
+            }
             arrow::datatypes::DataType::List(field) => {
-                DataType::List(Box::new(Self::from_arrow_data_type(field.data_type())))
+                DataType::List(Box::new(DataType::try_from(field.data_type())?))
             }
             arrow::datatypes::DataType::LargeList(field) => {
-                DataType::LargeList(Box::new(Self::from_arrow_data_type(field.data_type())))
+                DataType::LargeList(Box::new(DataType::try_from(field.data_type())?))
+            }
+            arrow::datatypes::DataType::ListView(field) => {
+                DataType::ListView(Box::new(DataType::try_from(field.data_type())?))
+            }
+            arrow::datatypes::DataType::LargeListView(field) => {
+                DataType::LargeListView(Box::new(DataType::try_from(field.data_type())?))
             }
+            arrow::datatypes::DataType::FixedSizeList(field, length) => DataType::FixedSizeList(
+                Box::new(DataType::try_from(field.data_type())?),
+                *length,
+            ),
             arrow::datatypes::DataType::Map(field, _keys_sorted) => {
                 let arrow::datatypes::DataType::Struct(fields) = field.data_type() else {
-                    // TODO: error handling
-                    panic!("Expected DataType::Struct but found something else")
+                    return Err(SchemaError::MalformedMap(format!("{:?}", field.data_type())));
+                };
+                let [key_field, value_field] = &fields[..] else {
+                    return Err(SchemaError::MalformedMap(format!("{:?}", field.data_type())));
                 };
-                let key_type = Self::from_arrow_data_type(fields[0].data_type());
-                let value_type = Self::from_arrow_data_type(fields[1].data_type());
+                let key_type = DataType::try_from(key_field.data_type())?;
+                let value_type = DataType::try_from(value_field.data_type())?;
                 DataType::Map(Box::new(key_type), Box::new(value_type))
             }
-            arrow::datatypes::DataType::Struct(fields) => DataType::Struct(
-                fields
-                    .iter()
-                    .map(|field| {
-                        (
-                            field.name().to_string(),
-                            Self::from_arrow_data_type(field.data_type()),
-                        )
-                    })
-                    .collect::<Vec<(String, DataType)>>(),
-            ),
+            arrow::datatypes::DataType::Struct(fields) => {
+                let mut struct_fields = Vec::<(String, DataType)>::with_capacity(fields.len());
+                for field in fields {
+                    struct_fields
+                        .push((field.name().to_string(), DataType::try_from(field.data_type())?));
+                }
+                DataType::Struct(struct_fields)
+            }
             arrow::datatypes::DataType::Union(union_fields, mode) => {
-                let types = union_fields
-                    .iter()
-                    .map(|(type_id, field_ref)| {
-                        (type_id, Self::from_arrow_data_type(field_ref.data_type()))
-                    })
-                    .collect();
+                let mut types = Vec::<(i8, DataType)>::with_capacity(union_fields.len());
+                for (type_id, field_ref) in union_fields.iter() {
+                    types.push((type_id, DataType::try_from(field_ref.data_type())?));
+                }
                 DataType::Union(UnionType {
                     types,
                     mode: UnionMode::from_arrow_union_mode(*mode),
                 })
             }
-            _ => DataType::Unknown,
-        }
+            arrow::datatypes::DataType::Dictionary(index_type, value_type) => DataType::Dictionary(
+                Box::new(DataType::try_from(index_type.as_ref())?),
+                Box::new(DataType::try_from(value_type.as_ref())?),
+            ),
+            arrow::datatypes::DataType::RunEndEncoded(run_ends_field, values_field) => {
+                DataType::RunEndEncoded(
+                    Box::new(DataType::try_from(run_ends_field.data_type())?),
+                    Box::new(DataType::try_from(values_field.data_type())?),
+                )
+            }
+            other => {
+                return Err(SchemaError::UnsupportedArrowDataType(format!("{other:?}")))
+            }
+        })
     }
 }
 
@@ -368,24 +537,51 @@ pub struct Field {
     #[serde(rename = "dataType")]
     pub data_type: DataType,
     pub nullable: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<HashMap<String, String>>,
 }
 
 impl Field {
-    #[allow(dead_code)]
-    fn to_arrow_field(&self) -> Arc<arrow::datatypes::Field> {
-        Arc::new(arrow::datatypes::Field::new(
+    fn to_arrow_field(&self) -> Result<Arc<arrow::datatypes::Field>, SchemaError> {
+        let data_type = arrow::datatypes::DataType::try_from(&self.data_type).map_err(|source| {
+            SchemaError::Field {
+                name: self.name.clone(),
+                source: Box::new(source),
+            }
+        })?;
+
+        let field = arrow::datatypes::Field::new(
             self.name.clone(),
-            self.data_type.to_arrow_data_type(),
+            data_type,
             self.nullable.unwrap_or(true),
-        ))
+        );
+
+        Ok(Arc::new(match &self.metadata {
+            Some(metadata) => field.with_metadata(metadata.clone()),
+            None => field,
+        }))
     }
 
-    pub fn from_arrow_field(field: &arrow::datatypes::Field) -> Self {
-        Self {
+    pub fn from_arrow_field(field: &arrow::datatypes::Field) -> Result<Self, SchemaError> {
+        let data_type = DataType::try_from(field.data_type()).map_err(|source| {
+            SchemaError::Field {
+                name: field.name().clone(),
+                source: Box::new(source),
+            }
+        })?;
+
+        let metadata = field.metadata();
+
+        Ok(Self {
             name: field.name().clone(),
-            data_type: DataType::from_arrow_data_type(field.data_type()),
+            data_type,
             nullable: Some(field.is_nullable()),
-        }
+            metadata: if metadata.is_empty() {
+                None
+            } else {
+                Some(metadata.clone())
+            },
+        })
     }
 }
 
@@ -394,30 +590,52 @@ impl Field {
 #[allow(clippy::module_name_repetitions)]
 pub struct DataSourceSchema {
     pub fields: Vec<Field>,
+    // Schema-level metadata is carried alongside the fields in memory but kept
+    // out of the wire representation, so the JSON stays the historical bare
+    // `[ {field}, … ]` array rather than becoming an object. It is populated
+    // from arrow schemas and flows back out through the `TryFrom` conversion
+    // into an arrow `Schema`.
+    #[serde(skip)]
+    pub metadata: Option<HashMap<String, String>>,
 }
 
-impl DataSourceSchema {
-    pub fn to_arrow_schema(&self) -> arrow::datatypes::Schema {
-        let mut schema_fields = Vec::<arrow::datatypes::Field>::new();
+impl TryFrom<&DataSourceSchema> for arrow::datatypes::Schema {
+    type Error = SchemaError;
 
-        for field in &self.fields {
-            schema_fields.push(arrow::datatypes::Field::new(
-                field.name.clone(),
-                field.data_type.to_arrow_data_type(),
-                field.nullable.unwrap_or(true),
-            ));
+    fn try_from(schema: &DataSourceSchema) -> Result<Self, Self::Error> {
+        let mut schema_fields =
+            Vec::<arrow::datatypes::Field>::with_capacity(schema.fields.len());
+
+        for field in &schema.fields {
+            schema_fields.push(arrow::datatypes::Field::clone(&field.to_arrow_field()?));
         }
 
-        arrow::datatypes::Schema::new(schema_fields)
+        Ok(arrow::datatypes::Schema::new_with_metadata(
+            schema_fields,
+            schema.metadata.clone().unwrap_or_default(),
+        ))
     }
+}
 
-    pub fn from_arrow_schema(schema: &SchemaRef) -> Self {
-        let mut fields = Vec::<Field>::new();
+impl TryFrom<&SchemaRef> for DataSourceSchema {
+    type Error = SchemaError;
+
+    fn try_from(schema: &SchemaRef) -> Result<Self, Self::Error> {
+        let mut fields = Vec::<Field>::with_capacity(schema.fields.len());
 
         for field in &schema.fields {
-            fields.push(Field::from_arrow_field(field));
+            fields.push(Field::from_arrow_field(field)?);
         }
 
-        Self { fields }
+        let metadata = schema.metadata();
+
+        Ok(Self {
+            fields,
+            metadata: if metadata.is_empty() {
+                None
+            } else {
+                Some(metadata.clone())
+            },
+        })
     }
 }
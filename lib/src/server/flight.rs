@@ -0,0 +1,134 @@
+// flight - Creates and registers the Arrow Flight SQL server
+// Sasaki, Naoki <nsasaki@sal.co.jp> July 25, 2026
+//
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use arrow_flight::encode::FlightDataEncoderBuilder;
+use arrow_flight::flight_service_server::{FlightService, FlightServiceServer};
+use arrow_flight::sql::server::FlightSqlService;
+use arrow_flight::sql::{CommandStatementQuery, ProstMessageExt, SqlInfo, TicketStatementQuery};
+use arrow_flight::{FlightDescriptor, FlightEndpoint, FlightInfo, Ticket};
+use datafusion::arrow::{self, datatypes::SchemaRef, record_batch::RecordBatch};
+use futures::{stream, TryStreamExt};
+use prost::Message;
+use tonic::{Request, Response, Status};
+
+use crate::context::session_manager::SessionManager;
+use crate::data_source::schema::DataSourceSchema;
+use crate::settings::Settings;
+
+/// Arrow Flight SQL surface backed by the same [`SessionManager`] as the REST
+/// server, so clients can submit SQL and stream Arrow record batches over gRPC
+/// without the JSON encode/decode hop.
+pub struct FlightSqlServer<S: SessionManager> {
+    session_mgr: Arc<tokio::sync::Mutex<S>>,
+}
+
+impl<S: SessionManager> FlightSqlServer<S> {
+    fn new(session_mgr: Arc<tokio::sync::Mutex<S>>) -> Self {
+        Self { session_mgr }
+    }
+
+    /// Plans the statement and returns its result schema **without** executing
+    /// it, so `GetFlightInfo` never triggers the side effects of a DDL/DML
+    /// statement nor materializes a query's result set.
+    async fn plan_schema(&self, query: &str) -> Result<SchemaRef, Status> {
+        self.session_mgr
+            .lock()
+            .await
+            .plan_sql(query)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))
+    }
+
+    async fn execute(&self, query: &str) -> Result<Vec<RecordBatch>, Status> {
+        self.session_mgr
+            .lock()
+            .await
+            .execute_sql(query)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))
+    }
+}
+
+#[tonic::async_trait]
+impl<S: SessionManager> FlightSqlService for FlightSqlServer<S> {
+    type FlightService = FlightSqlServer<S>;
+
+    async fn get_flight_info_statement(
+        &self,
+        query: CommandStatementQuery,
+        request: Request<FlightDescriptor>,
+    ) -> Result<Response<FlightInfo>, Status> {
+        let schema = self.plan_schema(&query.query).await?;
+        let arrow_schema = normalize_schema(&schema);
+
+        let ticket = TicketStatementQuery {
+            statement_handle: query.query.into_bytes().into(),
+        };
+
+        let endpoint = FlightEndpoint::new().with_ticket(Ticket {
+            ticket: ticket.as_any().encode_to_vec().into(),
+        });
+
+        let info = FlightInfo::new()
+            .try_with_schema(&arrow_schema)
+            .map_err(|e| Status::internal(e.to_string()))?
+            .with_endpoint(endpoint)
+            .with_descriptor(request.into_inner());
+
+        Ok(Response::new(info))
+    }
+
+    async fn do_get_statement(
+        &self,
+        ticket: TicketStatementQuery,
+        _request: Request<Ticket>,
+    ) -> Result<Response<<Self::FlightService as FlightService>::DoGetStream>, Status> {
+        let query = String::from_utf8(ticket.statement_handle.to_vec())
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+        let batches = self.execute(&query).await?;
+        // Stream the batches under their own schema; the encoder must see the
+        // exact arrow types (names/nullability of nested children included)
+        // that the arrays carry. Schema normalization is only for the schema
+        // advertised by GetFlightInfo, never for the data transport here.
+        let arrow_schema = batches.first().map_or_else(
+            || Arc::new(arrow::datatypes::Schema::empty()),
+            RecordBatch::schema,
+        );
+
+        let stream = FlightDataEncoderBuilder::new()
+            .with_schema(arrow_schema)
+            .build(stream::iter(batches.into_iter().map(Ok)))
+            .map_err(|e| Status::internal(e.to_string()));
+
+        Ok(Response::new(Box::pin(stream)))
+    }
+
+    async fn register_sql_info(&self, _id: i32, _result: &SqlInfo) {}
+}
+
+/// Normalizes an Arrow schema by routing it through [`DataSourceSchema`], so
+/// the Flight surface reports the exact same schema shape the REST surface
+/// does. Falls back to the original schema if it contains a type the wire
+/// model cannot represent.
+fn normalize_schema(schema: &SchemaRef) -> Arc<arrow::datatypes::Schema> {
+    DataSourceSchema::try_from(schema)
+        .and_then(|ds| arrow::datatypes::Schema::try_from(&ds))
+        .map_or_else(|_| schema.clone(), Arc::new)
+}
+
+/// Builds the Arrow Flight SQL service and the socket address of its configured
+/// port, to be served alongside the HTTP listener created by
+/// [`super::http::create_server`]. It binds no listener itself; the caller
+/// serves the returned service on the returned address.
+pub async fn create_server<S: SessionManager>(
+    session_mgr: Arc<tokio::sync::Mutex<S>>,
+) -> Result<(FlightServiceServer<FlightSqlServer<S>>, SocketAddr), anyhow::Error> {
+    let service = FlightServiceServer::new(FlightSqlServer::new(session_mgr));
+    let sock_addr = SocketAddr::from(([0, 0, 0, 0], Settings::global().server.flight_port));
+
+    Ok((service, sock_addr))
+}